@@ -0,0 +1,49 @@
+pub mod request;
+
+use couchbase_sys::*;
+use request::{
+    counter_callback, remove_callback, store_callback, subdoc_callback, touch_callback,
+    unlock_callback,
+};
+
+/// Installs the response callbacks the request types in [`request`] rely on
+/// to resolve their `oneshot::Sender`s. Must run once, immediately after the
+/// `lcb_INSTANCE` is created and before any request is encoded, alongside
+/// whatever callbacks the GET path (`lcb_get`) already installs.
+pub(crate) unsafe fn install_callbacks(instance: *mut lcb_INSTANCE) {
+    lcb_install_callback3(
+        instance,
+        lcb_CALLBACK_TYPE_LCB_CALLBACK_STORE as i32,
+        Some(store_callback),
+    );
+    lcb_install_callback3(
+        instance,
+        lcb_CALLBACK_TYPE_LCB_CALLBACK_REMOVE as i32,
+        Some(remove_callback),
+    );
+    lcb_install_callback3(
+        instance,
+        lcb_CALLBACK_TYPE_LCB_CALLBACK_TOUCH as i32,
+        Some(touch_callback),
+    );
+    lcb_install_callback3(
+        instance,
+        lcb_CALLBACK_TYPE_LCB_CALLBACK_COUNTER as i32,
+        Some(counter_callback),
+    );
+    lcb_install_callback3(
+        instance,
+        lcb_CALLBACK_TYPE_LCB_CALLBACK_SDLOOKUP as i32,
+        Some(subdoc_callback),
+    );
+    lcb_install_callback3(
+        instance,
+        lcb_CALLBACK_TYPE_LCB_CALLBACK_SDMUTATE as i32,
+        Some(subdoc_callback),
+    );
+    lcb_install_callback3(
+        instance,
+        lcb_CALLBACK_TYPE_LCB_CALLBACK_UNLOCK as i32,
+        Some(unlock_callback),
+    );
+}
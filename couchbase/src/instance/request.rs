@@ -5,11 +5,54 @@ use futures::sync::oneshot::Sender;
 use std::ffi::{c_void, CString};
 use std::os::raw::c_char;
 use std::ptr;
+use std::time::Duration;
 
 pub trait InstanceRequest: Send + 'static {
     fn encode(self: Box<Self>, instance: *mut lcb_INSTANCE);
 }
 
+/// Cookie handed to `lcb_store` for mutation requests carrying a value.
+/// `lcb_cmdstore_value` records the pointer without copying or taking
+/// ownership of the bytes, so the backing buffer is kept alive here until the
+/// store callback fires, at which point it is reclaimed along with the sender.
+struct StoreCookie {
+    sender: Sender<MutationResult>,
+    value: Vec<u8>,
+}
+
+/// Callback for `LCB_CALLBACK_STORE`, installed by
+/// [`super::install_callbacks`] when the instance is created. Reclaims the
+/// `StoreCookie` boxed in `UpsertRequest`, `InsertRequest` and
+/// `ReplaceRequest`, surfaces the CAS the server handed back on the
+/// resulting `MutationResult`, and drops the held `value` buffer now that
+/// libcouchbase no longer needs it.
+pub(crate) unsafe extern "C" fn store_callback(
+    _instance: *mut lcb_INSTANCE,
+    _cbtype: i32,
+    res: *const lcb_RESPSTORE,
+) {
+    let mut raw_cookie: *mut c_void = ptr::null_mut();
+    lcb_respstore_cookie(res, &mut raw_cookie);
+    let cookie = Box::from_raw(raw_cookie as *mut StoreCookie);
+
+    let mut cas: u64 = 0;
+    lcb_respstore_cas(res, &mut cas);
+
+    let _ = cookie.sender.send(MutationResult::new(cas));
+}
+
+/// libcouchbase expiry and lock-time fields are whole seconds. Round a
+/// sub-second `Duration` up to 1 rather than truncating it to 0, which
+/// would silently turn a short-lived expiry/lock into "none".
+fn secs_ceil(duration: Duration) -> u32 {
+    let secs = duration.as_secs();
+    if secs == 0 && duration.subsec_nanos() > 0 {
+        1
+    } else {
+        secs as u32
+    }
+}
+
 #[derive(Debug)]
 pub struct GetRequest {
     sender: Sender<Option<GetResult>>,
@@ -73,6 +116,19 @@ impl UpsertRequest {
             options,
         }
     }
+
+    /// Encodes `value` with `transcoder`, deriving `content` and `flags`
+    /// together instead of requiring the caller to supply `flags` by hand.
+    pub fn with_transcoder<T: Transcoder>(
+        sender: Sender<MutationResult>,
+        id: String,
+        value: T::Value,
+        transcoder: &T,
+        options: Option<UpsertOptions>,
+    ) -> Self {
+        let (content, flags) = transcoder.encode(value);
+        Self::new(sender, id, content, flags, options)
+    }
 }
 
 impl InstanceRequest for UpsertRequest {
@@ -82,21 +138,38 @@ impl InstanceRequest for UpsertRequest {
 
         let mut command: *mut lcb_CMDSTORE = ptr::null_mut();
 
-        let sender_boxed = Box::new(self.sender);
-        let cookie = Box::into_raw(sender_boxed) as *mut c_void;
-
         let value_len = self.content.len();
-        let value = CString::new(self.content).expect("Could not turn value into lcb format");
+        let value_ptr = self.content.as_ptr() as *const c_char;
+        let cookie_boxed = Box::new(StoreCookie {
+            sender: self.sender,
+            value: self.content,
+        });
+        let cookie = Box::into_raw(cookie_boxed) as *mut c_void;
 
         unsafe {
             lcb_cmdstore_create(&mut command, lcb_STORE_OPERATION_LCB_STORE_UPSERT);
             lcb_cmdstore_key(command, id_encoded.as_ptr(), id_len);
             lcb_cmdstore_flags(command, self.flags);
-            lcb_cmdstore_value(command, value.into_raw() as *const c_char, value_len);
+            lcb_cmdstore_value(command, value_ptr, value_len);
             if let Some(options) = self.options {
                 if let Some(timeout) = options.timeout() {
                     lcb_cmdstore_timeout(command, timeout.as_millis() as u32);
                 }
+                if let Some(cas) = options.cas() {
+                    lcb_cmdstore_cas(command, cas);
+                }
+                if let Some(expiry) = options.expiry() {
+                    lcb_cmdstore_expiry(command, secs_ceil(expiry));
+                }
+                if let Some(level) = options.durability_level() {
+                    lcb_cmdstore_durability(command, level);
+                } else if options.persist_to().is_some() || options.replicate_to().is_some() {
+                    lcb_cmdstore_durability_observe(
+                        command,
+                        options.persist_to().unwrap_or(0),
+                        options.replicate_to().unwrap_or(0),
+                    );
+                }
             }
             lcb_store(instance, cookie, command);
         }
@@ -128,6 +201,19 @@ impl InsertRequest {
             options,
         }
     }
+
+    /// Encodes `value` with `transcoder`, deriving `content` and `flags`
+    /// together instead of requiring the caller to supply `flags` by hand.
+    pub fn with_transcoder<T: Transcoder>(
+        sender: Sender<MutationResult>,
+        id: String,
+        value: T::Value,
+        transcoder: &T,
+        options: Option<InsertOptions>,
+    ) -> Self {
+        let (content, flags) = transcoder.encode(value);
+        Self::new(sender, id, content, flags, options)
+    }
 }
 
 impl InstanceRequest for InsertRequest {
@@ -137,21 +223,35 @@ impl InstanceRequest for InsertRequest {
 
         let mut command: *mut lcb_CMDSTORE = ptr::null_mut();
 
-        let sender_boxed = Box::new(self.sender);
-        let cookie = Box::into_raw(sender_boxed) as *mut c_void;
-
         let value_len = self.content.len();
-        let value = CString::new(self.content).expect("Could not turn value into lcb format");
+        let value_ptr = self.content.as_ptr() as *const c_char;
+        let cookie_boxed = Box::new(StoreCookie {
+            sender: self.sender,
+            value: self.content,
+        });
+        let cookie = Box::into_raw(cookie_boxed) as *mut c_void;
 
         unsafe {
             lcb_cmdstore_create(&mut command, lcb_STORE_OPERATION_LCB_STORE_ADD);
             lcb_cmdstore_key(command, id_encoded.as_ptr(), id_len);
             lcb_cmdstore_flags(command, self.flags);
-            lcb_cmdstore_value(command, value.into_raw() as *const c_char, value_len);
+            lcb_cmdstore_value(command, value_ptr, value_len);
             if let Some(options) = self.options {
                 if let Some(timeout) = options.timeout() {
                     lcb_cmdstore_timeout(command, timeout.as_millis() as u32);
                 }
+                if let Some(expiry) = options.expiry() {
+                    lcb_cmdstore_expiry(command, secs_ceil(expiry));
+                }
+                if let Some(level) = options.durability_level() {
+                    lcb_cmdstore_durability(command, level);
+                } else if options.persist_to().is_some() || options.replicate_to().is_some() {
+                    lcb_cmdstore_durability_observe(
+                        command,
+                        options.persist_to().unwrap_or(0),
+                        options.replicate_to().unwrap_or(0),
+                    );
+                }
             }
             lcb_store(instance, cookie, command);
         }
@@ -183,6 +283,19 @@ impl ReplaceRequest {
             options,
         }
     }
+
+    /// Encodes `value` with `transcoder`, deriving `content` and `flags`
+    /// together instead of requiring the caller to supply `flags` by hand.
+    pub fn with_transcoder<T: Transcoder>(
+        sender: Sender<MutationResult>,
+        id: String,
+        value: T::Value,
+        transcoder: &T,
+        options: Option<ReplaceOptions>,
+    ) -> Self {
+        let (content, flags) = transcoder.encode(value);
+        Self::new(sender, id, content, flags, options)
+    }
 }
 
 impl InstanceRequest for ReplaceRequest {
@@ -192,27 +305,63 @@ impl InstanceRequest for ReplaceRequest {
 
         let mut command: *mut lcb_CMDSTORE = ptr::null_mut();
 
-        let sender_boxed = Box::new(self.sender);
-        let cookie = Box::into_raw(sender_boxed) as *mut c_void;
-
         let value_len = self.content.len();
-        let value = CString::new(self.content).expect("Could not turn value into lcb format");
+        let value_ptr = self.content.as_ptr() as *const c_char;
+        let cookie_boxed = Box::new(StoreCookie {
+            sender: self.sender,
+            value: self.content,
+        });
+        let cookie = Box::into_raw(cookie_boxed) as *mut c_void;
 
         unsafe {
             lcb_cmdstore_create(&mut command, lcb_STORE_OPERATION_LCB_STORE_REPLACE);
             lcb_cmdstore_key(command, id_encoded.as_ptr(), id_len);
             lcb_cmdstore_flags(command, self.flags);
-            lcb_cmdstore_value(command, value.into_raw() as *const c_char, value_len);
+            lcb_cmdstore_value(command, value_ptr, value_len);
             if let Some(options) = self.options {
                 if let Some(timeout) = options.timeout() {
                     lcb_cmdstore_timeout(command, timeout.as_millis() as u32);
                 }
+                if let Some(cas) = options.cas() {
+                    lcb_cmdstore_cas(command, cas);
+                }
+                if let Some(expiry) = options.expiry() {
+                    lcb_cmdstore_expiry(command, secs_ceil(expiry));
+                }
+                if let Some(level) = options.durability_level() {
+                    lcb_cmdstore_durability(command, level);
+                } else if options.persist_to().is_some() || options.replicate_to().is_some() {
+                    lcb_cmdstore_durability_observe(
+                        command,
+                        options.persist_to().unwrap_or(0),
+                        options.replicate_to().unwrap_or(0),
+                    );
+                }
             }
             lcb_store(instance, cookie, command);
         }
     }
 }
 
+/// Callback for `LCB_CALLBACK_REMOVE`, installed by
+/// [`super::install_callbacks`] when the instance is created. Reclaims the
+/// `Sender<MutationResult>` boxed in `RemoveRequest` and surfaces the CAS
+/// the server handed back.
+pub(crate) unsafe extern "C" fn remove_callback(
+    _instance: *mut lcb_INSTANCE,
+    _cbtype: i32,
+    res: *const lcb_RESPREMOVE,
+) {
+    let mut raw_cookie: *mut c_void = ptr::null_mut();
+    lcb_respremove_cookie(res, &mut raw_cookie);
+    let sender = Box::from_raw(raw_cookie as *mut Sender<MutationResult>);
+
+    let mut cas: u64 = 0;
+    lcb_respremove_cas(res, &mut cas);
+
+    let _ = sender.send(MutationResult::new(cas));
+}
+
 #[derive(Debug)]
 pub struct RemoveRequest {
     sender: Sender<MutationResult>,
@@ -245,8 +394,710 @@ impl InstanceRequest for RemoveRequest {
                 if let Some(timeout) = options.timeout() {
                     lcb_cmdremove_timeout(command, timeout.as_millis() as u32);
                 }
+                if let Some(cas) = options.cas() {
+                    lcb_cmdremove_cas(command, cas);
+                }
+                if let Some(level) = options.durability_level() {
+                    lcb_cmdremove_durability(command, level);
+                } else if options.persist_to().is_some() || options.replicate_to().is_some() {
+                    lcb_cmdremove_durability_observe(
+                        command,
+                        options.persist_to().unwrap_or(0),
+                        options.replicate_to().unwrap_or(0),
+                    );
+                }
             }
             lcb_remove(instance, cookie, command);
         }
     }
 }
+
+/// Callback for `LCB_CALLBACK_TOUCH`, installed by
+/// [`super::install_callbacks`] when the instance is created. Reclaims the
+/// `Sender<MutationResult>` boxed in `TouchRequest` and surfaces the CAS
+/// the server handed back.
+pub(crate) unsafe extern "C" fn touch_callback(
+    _instance: *mut lcb_INSTANCE,
+    _cbtype: i32,
+    res: *const lcb_RESPTOUCH,
+) {
+    let mut raw_cookie: *mut c_void = ptr::null_mut();
+    lcb_resptouch_cookie(res, &mut raw_cookie);
+    let sender = Box::from_raw(raw_cookie as *mut Sender<MutationResult>);
+
+    let mut cas: u64 = 0;
+    lcb_resptouch_cas(res, &mut cas);
+
+    let _ = sender.send(MutationResult::new(cas));
+}
+
+#[derive(Debug)]
+pub struct TouchRequest {
+    sender: Sender<MutationResult>,
+    id: String,
+    expiry: Duration,
+    options: Option<TouchOptions>,
+}
+
+impl TouchRequest {
+    pub fn new(
+        sender: Sender<MutationResult>,
+        id: String,
+        expiry: Duration,
+        options: Option<TouchOptions>,
+    ) -> Self {
+        Self {
+            sender,
+            id,
+            expiry,
+            options,
+        }
+    }
+}
+
+impl InstanceRequest for TouchRequest {
+    fn encode(self: Box<Self>, instance: *mut lcb_INSTANCE) {
+        let id_len = self.id.len();
+        let id_encoded = CString::new(self.id).expect("Could not encode ID");
+        let mut command: *mut lcb_CMDTOUCH = ptr::null_mut();
+
+        let sender_boxed = Box::new(self.sender);
+        let cookie = Box::into_raw(sender_boxed) as *mut c_void;
+        unsafe {
+            lcb_cmdtouch_create(&mut command);
+            lcb_cmdtouch_key(command, id_encoded.as_ptr(), id_len);
+            lcb_cmdtouch_expiry(command, secs_ceil(self.expiry));
+            if let Some(options) = self.options {
+                if let Some(timeout) = options.timeout() {
+                    lcb_cmdtouch_timeout(command, timeout.as_millis() as u32);
+                }
+            }
+            lcb_touch(instance, cookie, command);
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct GetAndTouchRequest {
+    sender: Sender<Option<GetResult>>,
+    id: String,
+    expiry: Duration,
+    options: Option<GetOptions>,
+}
+
+impl GetAndTouchRequest {
+    pub fn new(
+        sender: Sender<Option<GetResult>>,
+        id: String,
+        expiry: Duration,
+        options: Option<GetOptions>,
+    ) -> Self {
+        Self {
+            sender,
+            id,
+            expiry,
+            options,
+        }
+    }
+}
+
+impl InstanceRequest for GetAndTouchRequest {
+    fn encode(self: Box<Self>, instance: *mut lcb_INSTANCE) {
+        let id_len = self.id.len();
+        let id_encoded = CString::new(self.id).expect("Could not encode ID");
+        let mut command: *mut lcb_CMDGET = ptr::null_mut();
+
+        let sender_boxed = Box::new(self.sender);
+        let cookie = Box::into_raw(sender_boxed) as *mut c_void;
+        unsafe {
+            lcb_cmdget_create(&mut command);
+            lcb_cmdget_key(command, id_encoded.as_ptr(), id_len);
+            lcb_cmdget_expiry(command, secs_ceil(self.expiry));
+            if let Some(options) = self.options {
+                if let Some(timeout) = options.timeout() {
+                    lcb_cmdget_timeout(command, timeout.as_millis() as u32);
+                }
+            }
+            lcb_get(instance, cookie, command);
+        }
+    }
+}
+
+/// Callback for `LCB_CALLBACK_COUNTER`, installed by
+/// [`super::install_callbacks`] when the instance is created. Reclaims the
+/// `Sender<CounterResult>` boxed in `CounterRequest` and surfaces the
+/// resulting value and CAS the server handed back.
+pub(crate) unsafe extern "C" fn counter_callback(
+    _instance: *mut lcb_INSTANCE,
+    _cbtype: i32,
+    res: *const lcb_RESPCOUNTER,
+) {
+    let mut raw_cookie: *mut c_void = ptr::null_mut();
+    lcb_respcounter_cookie(res, &mut raw_cookie);
+    let sender = Box::from_raw(raw_cookie as *mut Sender<CounterResult>);
+
+    let mut value: u64 = 0;
+    lcb_respcounter_value(res, &mut value);
+    let mut cas: u64 = 0;
+    lcb_respcounter_cas(res, &mut cas);
+
+    let _ = sender.send(CounterResult::new(value, cas));
+}
+
+#[derive(Debug)]
+pub struct CounterRequest {
+    sender: Sender<CounterResult>,
+    id: String,
+    delta: i64,
+    initial: Option<i64>,
+    expiry: Option<Duration>,
+    options: Option<CounterOptions>,
+}
+
+impl CounterRequest {
+    pub fn new(
+        sender: Sender<CounterResult>,
+        id: String,
+        delta: i64,
+        initial: Option<i64>,
+        expiry: Option<Duration>,
+        options: Option<CounterOptions>,
+    ) -> Self {
+        Self {
+            sender,
+            id,
+            delta,
+            initial,
+            expiry,
+            options,
+        }
+    }
+}
+
+impl InstanceRequest for CounterRequest {
+    fn encode(self: Box<Self>, instance: *mut lcb_INSTANCE) {
+        let id_len = self.id.len();
+        let id_encoded = CString::new(self.id).expect("Could not encode ID");
+        let mut command: *mut lcb_CMDCOUNTER = ptr::null_mut();
+
+        let sender_boxed = Box::new(self.sender);
+        let cookie = Box::into_raw(sender_boxed) as *mut c_void;
+        unsafe {
+            lcb_cmdcounter_create(&mut command);
+            lcb_cmdcounter_key(command, id_encoded.as_ptr(), id_len);
+            lcb_cmdcounter_delta(command, self.delta);
+            if let Some(initial) = self.initial {
+                lcb_cmdcounter_initial(command, initial);
+            }
+            if let Some(expiry) = self.expiry {
+                lcb_cmdcounter_expiry(command, secs_ceil(expiry));
+            }
+            if let Some(options) = self.options {
+                if let Some(timeout) = options.timeout() {
+                    lcb_cmdcounter_timeout(command, timeout.as_millis() as u32);
+                }
+            }
+            lcb_counter(instance, cookie, command);
+        }
+    }
+}
+
+/// A lightweight type-conversion layer for interpreting the raw byte payload
+/// of a document (most commonly a counter) as a concrete Rust type, instead
+/// of forcing every caller to parse `GetResult` content by hand.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Conversion {
+    Integer,
+    Float,
+    Boolean,
+    Bytes,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Converted {
+    Integer(i64),
+    Float(f64),
+    Boolean(bool),
+    Bytes(Vec<u8>),
+}
+
+#[derive(Debug)]
+pub enum ConversionError {
+    Malformed,
+}
+
+impl Conversion {
+    pub fn convert(self, raw: &[u8]) -> Result<Converted, ConversionError> {
+        match self {
+            Conversion::Bytes => Ok(Converted::Bytes(raw.to_vec())),
+            Conversion::Integer => std::str::from_utf8(raw)
+                .ok()
+                .and_then(|s| s.trim().parse::<i64>().ok())
+                .map(Converted::Integer)
+                .ok_or(ConversionError::Malformed),
+            Conversion::Float => std::str::from_utf8(raw)
+                .ok()
+                .and_then(|s| s.trim().parse::<f64>().ok())
+                .map(Converted::Float)
+                .ok_or(ConversionError::Malformed),
+            Conversion::Boolean => match raw {
+                b"true" => Ok(Converted::Boolean(true)),
+                b"false" => Ok(Converted::Boolean(false)),
+                _ => Err(ConversionError::Malformed),
+            },
+        }
+    }
+}
+
+/// Interprets a `GetResult`'s content through a requested `Conversion`,
+/// e.g. reading a counter document back as a typed `i64`.
+pub trait ConvertContent {
+    fn convert(&self, conversion: Conversion) -> Result<Converted, ConversionError>;
+}
+
+impl ConvertContent for GetResult {
+    fn convert(&self, conversion: Conversion) -> Result<Converted, ConversionError> {
+        conversion.convert(self.content())
+    }
+}
+
+/// A single path-scoped operation within a `SubdocRequest`.
+#[derive(Debug, Clone)]
+pub struct SubdocSpec {
+    path: String,
+    op: SubdocOp,
+    create_path: bool,
+    xattr: bool,
+}
+
+#[derive(Debug, Clone)]
+pub enum SubdocOp {
+    Get,
+    Exists,
+    DictUpsert(Vec<u8>),
+    ArrayAppend(Vec<u8>),
+    Counter(i64),
+    Remove,
+}
+
+impl SubdocSpec {
+    pub fn get(path: impl Into<String>) -> Self {
+        Self::new(path, SubdocOp::Get)
+    }
+
+    pub fn exists(path: impl Into<String>) -> Self {
+        Self::new(path, SubdocOp::Exists)
+    }
+
+    pub fn dict_upsert(path: impl Into<String>, value: Vec<u8>) -> Self {
+        Self::new(path, SubdocOp::DictUpsert(value))
+    }
+
+    pub fn array_append(path: impl Into<String>, value: Vec<u8>) -> Self {
+        Self::new(path, SubdocOp::ArrayAppend(value))
+    }
+
+    pub fn counter(path: impl Into<String>, delta: i64) -> Self {
+        Self::new(path, SubdocOp::Counter(delta))
+    }
+
+    pub fn remove(path: impl Into<String>) -> Self {
+        Self::new(path, SubdocOp::Remove)
+    }
+
+    fn new(path: impl Into<String>, op: SubdocOp) -> Self {
+        Self {
+            path: path.into(),
+            op,
+            create_path: false,
+            xattr: false,
+        }
+    }
+
+    pub fn create_path(mut self, create_path: bool) -> Self {
+        self.create_path = create_path;
+        self
+    }
+
+    pub fn xattr(mut self, xattr: bool) -> Self {
+        self.xattr = xattr;
+        self
+    }
+
+    fn option_flags(&self) -> u32 {
+        let mut flags = 0;
+        if self.create_path {
+            flags |= lcb_SUBDOCSPECS_F_MKINTERMEDIATES;
+        }
+        if self.xattr {
+            flags |= lcb_SUBDOCSPECS_F_XATTRPATH;
+        }
+        flags
+    }
+}
+
+/// Callback for `LCB_CALLBACK_SDLOOKUP` and `LCB_CALLBACK_SDMUTATE`,
+/// installed by [`super::install_callbacks`] when the instance is created.
+/// `lcb_subdoc` fires one or the other depending on whether any spec in the
+/// request mutates, so both callback types are routed to this function.
+/// Reclaims the `Sender<SubdocResult>` boxed in `SubdocRequest` and
+/// surfaces the CAS the server handed back.
+pub(crate) unsafe extern "C" fn subdoc_callback(
+    _instance: *mut lcb_INSTANCE,
+    _cbtype: i32,
+    res: *const lcb_RESPSUBDOC,
+) {
+    let mut raw_cookie: *mut c_void = ptr::null_mut();
+    lcb_respsubdoc_cookie(res, &mut raw_cookie);
+    let sender = Box::from_raw(raw_cookie as *mut Sender<SubdocResult>);
+
+    let mut cas: u64 = 0;
+    lcb_respsubdoc_cas(res, &mut cas);
+
+    let _ = sender.send(SubdocResult::new(cas));
+}
+
+#[derive(Debug)]
+pub struct SubdocRequest {
+    sender: Sender<SubdocResult>,
+    id: String,
+    specs: Vec<SubdocSpec>,
+    options: Option<SubdocOptions>,
+}
+
+impl SubdocRequest {
+    pub fn new(
+        sender: Sender<SubdocResult>,
+        id: String,
+        specs: Vec<SubdocSpec>,
+        options: Option<SubdocOptions>,
+    ) -> Self {
+        Self {
+            sender,
+            id,
+            specs,
+            options,
+        }
+    }
+}
+
+impl InstanceRequest for SubdocRequest {
+    fn encode(self: Box<Self>, instance: *mut lcb_INSTANCE) {
+        let id_len = self.id.len();
+        let id_encoded = CString::new(self.id).expect("Could not encode ID");
+
+        let sender_boxed = Box::new(self.sender);
+        let cookie = Box::into_raw(sender_boxed) as *mut c_void;
+
+        unsafe {
+            let mut specs: *mut lcb_SUBDOCSPECS = ptr::null_mut();
+            lcb_subdocspecs_create(&mut specs, self.specs.len());
+
+            let encoded_paths: Vec<CString> = self
+                .specs
+                .iter()
+                .map(|spec| CString::new(spec.path.clone()).expect("Could not encode path"))
+                .collect();
+
+            for (index, (spec, path_encoded)) in self.specs.iter().zip(&encoded_paths).enumerate() {
+                let path_len = spec.path.len();
+                let flags = spec.option_flags();
+                match &spec.op {
+                    SubdocOp::Get => {
+                        lcb_subdocspecs_get(specs, index, flags, path_encoded.as_ptr(), path_len)
+                    }
+                    SubdocOp::Exists => {
+                        lcb_subdocspecs_exists(specs, index, flags, path_encoded.as_ptr(), path_len)
+                    }
+                    SubdocOp::DictUpsert(value) => lcb_subdocspecs_dict_upsert(
+                        specs,
+                        index,
+                        flags,
+                        path_encoded.as_ptr(),
+                        path_len,
+                        value.as_ptr() as *const c_char,
+                        value.len(),
+                    ),
+                    SubdocOp::ArrayAppend(value) => lcb_subdocspecs_array_add_last(
+                        specs,
+                        index,
+                        flags,
+                        path_encoded.as_ptr(),
+                        path_len,
+                        value.as_ptr() as *const c_char,
+                        value.len(),
+                    ),
+                    SubdocOp::Counter(delta) => lcb_subdocspecs_counter(
+                        specs,
+                        index,
+                        flags,
+                        path_encoded.as_ptr(),
+                        path_len,
+                        *delta,
+                    ),
+                    SubdocOp::Remove => {
+                        lcb_subdocspecs_remove(specs, index, flags, path_encoded.as_ptr(), path_len)
+                    }
+                };
+            }
+
+            let mut command: *mut lcb_CMDSUBDOC = ptr::null_mut();
+            lcb_cmdsubdoc_create(&mut command);
+            lcb_cmdsubdoc_key(command, id_encoded.as_ptr(), id_len);
+            lcb_cmdsubdoc_specs(command, specs);
+            if let Some(options) = self.options {
+                if let Some(timeout) = options.timeout() {
+                    lcb_cmdsubdoc_timeout(command, timeout.as_millis() as u32);
+                }
+            }
+            lcb_subdoc(instance, cookie, command);
+            lcb_subdocspecs_destroy(specs);
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct GetAndLockRequest {
+    sender: Sender<Option<GetResult>>,
+    id: String,
+    lock_time: Duration,
+    options: Option<GetOptions>,
+}
+
+impl GetAndLockRequest {
+    pub fn new(
+        sender: Sender<Option<GetResult>>,
+        id: String,
+        lock_time: Duration,
+        options: Option<GetOptions>,
+    ) -> Self {
+        Self {
+            sender,
+            id,
+            lock_time,
+            options,
+        }
+    }
+}
+
+impl InstanceRequest for GetAndLockRequest {
+    fn encode(self: Box<Self>, instance: *mut lcb_INSTANCE) {
+        let id_len = self.id.len();
+        let id_encoded = CString::new(self.id).expect("Could not encode ID");
+        let mut command: *mut lcb_CMDGET = ptr::null_mut();
+
+        let sender_boxed = Box::new(self.sender);
+        let cookie = Box::into_raw(sender_boxed) as *mut c_void;
+        unsafe {
+            lcb_cmdget_create(&mut command);
+            lcb_cmdget_key(command, id_encoded.as_ptr(), id_len);
+            lcb_cmdget_locktime(command, secs_ceil(self.lock_time));
+            if let Some(options) = self.options {
+                if let Some(timeout) = options.timeout() {
+                    lcb_cmdget_timeout(command, timeout.as_millis() as u32);
+                }
+            }
+            lcb_get(instance, cookie, command);
+        }
+    }
+}
+
+/// Callback for `LCB_CALLBACK_UNLOCK`, installed by
+/// [`super::install_callbacks`] when the instance is created. Reclaims the
+/// `Sender<MutationResult>` boxed in `UnlockRequest` and surfaces the CAS
+/// the server handed back.
+pub(crate) unsafe extern "C" fn unlock_callback(
+    _instance: *mut lcb_INSTANCE,
+    _cbtype: i32,
+    res: *const lcb_RESPUNLOCK,
+) {
+    let mut raw_cookie: *mut c_void = ptr::null_mut();
+    lcb_respunlock_cookie(res, &mut raw_cookie);
+    let sender = Box::from_raw(raw_cookie as *mut Sender<MutationResult>);
+
+    let mut cas: u64 = 0;
+    lcb_respunlock_cas(res, &mut cas);
+
+    let _ = sender.send(MutationResult::new(cas));
+}
+
+#[derive(Debug)]
+pub struct UnlockRequest {
+    sender: Sender<MutationResult>,
+    id: String,
+    cas: u64,
+    options: Option<UnlockOptions>,
+}
+
+impl UnlockRequest {
+    pub fn new(
+        sender: Sender<MutationResult>,
+        id: String,
+        cas: u64,
+        options: Option<UnlockOptions>,
+    ) -> Self {
+        Self {
+            sender,
+            id,
+            cas,
+            options,
+        }
+    }
+}
+
+impl InstanceRequest for UnlockRequest {
+    fn encode(self: Box<Self>, instance: *mut lcb_INSTANCE) {
+        let id_len = self.id.len();
+        let id_encoded = CString::new(self.id).expect("Could not encode ID");
+        let mut command: *mut lcb_CMDUNLOCK = ptr::null_mut();
+
+        let sender_boxed = Box::new(self.sender);
+        let cookie = Box::into_raw(sender_boxed) as *mut c_void;
+        unsafe {
+            lcb_cmdunlock_create(&mut command);
+            lcb_cmdunlock_key(command, id_encoded.as_ptr(), id_len);
+            lcb_cmdunlock_cas(command, self.cas);
+            if let Some(options) = self.options {
+                if let Some(timeout) = options.timeout() {
+                    lcb_cmdunlock_timeout(command, timeout.as_millis() as u32);
+                }
+            }
+            lcb_unlock(instance, cookie, command);
+        }
+    }
+}
+
+/// Retries a CAS-guarded mutation by rebuilding the request with the most
+/// recently observed CAS whenever `is_cas_mismatch` recognizes the failure,
+/// up to `max_attempts` attempts total. The first attempt is always made, so
+/// a `max_attempts` of 0 behaves like 1.
+///
+/// `attempt` receives the CAS to use (`None` on the first try) and performs
+/// one blocking request/response round-trip, e.g. fetching the current CAS,
+/// encoding a new request with it, and waiting on the request's
+/// `oneshot::Receiver`. `is_cas_mismatch` inspects a failed attempt and, if
+/// it represents a CAS mismatch, returns the CAS to retry with.
+pub fn retry_on_cas_mismatch<T, E>(
+    max_attempts: u32,
+    mut attempt: impl FnMut(Option<u64>) -> Result<T, E>,
+    mut is_cas_mismatch: impl FnMut(&E) -> Option<u64>,
+) -> Result<T, E> {
+    let mut cas = None;
+    let mut attempts = 0;
+    loop {
+        match attempt(cas) {
+            Ok(value) => return Ok(value),
+            Err(err) => {
+                attempts += 1;
+                match is_cas_mismatch(&err) {
+                    Some(latest_cas) if attempts < max_attempts => cas = Some(latest_cas),
+                    _ => return Err(err),
+                }
+            }
+        }
+    }
+}
+
+/// The data format a document's bytes were encoded with, carried in the
+/// lcb `flags` word so a later `get` knows how to decode them again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataFormat {
+    Json,
+    RawBinary,
+    RawString,
+}
+
+impl DataFormat {
+    const JSON_FLAG: u32 = 0x0200_0000;
+    const RAW_BINARY_FLAG: u32 = 0x0300_0000;
+    const RAW_STRING_FLAG: u32 = 0x0400_0000;
+
+    pub fn from_flags(flags: u32) -> Option<Self> {
+        match flags {
+            Self::JSON_FLAG => Some(DataFormat::Json),
+            Self::RAW_BINARY_FLAG => Some(DataFormat::RawBinary),
+            Self::RAW_STRING_FLAG => Some(DataFormat::RawString),
+            _ => None,
+        }
+    }
+
+    pub fn flags(self) -> u32 {
+        match self {
+            DataFormat::Json => Self::JSON_FLAG,
+            DataFormat::RawBinary => Self::RAW_BINARY_FLAG,
+            DataFormat::RawString => Self::RAW_STRING_FLAG,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum TranscodeError {
+    UnsupportedFormat(u32),
+    Malformed,
+}
+
+fn expect_format(result: &GetResult, accepted: &[DataFormat]) -> Result<(), TranscodeError> {
+    match DataFormat::from_flags(result.flags()) {
+        Some(format) if accepted.contains(&format) => Ok(()),
+        _ => Err(TranscodeError::UnsupportedFormat(result.flags())),
+    }
+}
+
+/// Encodes a typed Rust value into the `(content, flags)` pair a store
+/// request needs, and decodes a `GetResult` back into that type using the
+/// flags it was stored with, so `flags` doesn't have to be produced or
+/// interpreted by hand at every call site.
+pub trait Transcoder {
+    type Value;
+
+    fn encode(&self, value: Self::Value) -> (Vec<u8>, u32);
+    fn decode(&self, result: &GetResult) -> Result<Self::Value, TranscodeError>;
+}
+
+/// Tags already-serialized JSON bytes with the JSON data format flag; does
+/// not itself perform serialization, so it has no dependency on a JSON
+/// library.
+pub struct JsonTranscoder;
+
+impl Transcoder for JsonTranscoder {
+    type Value = Vec<u8>;
+
+    fn encode(&self, value: Vec<u8>) -> (Vec<u8>, u32) {
+        (value, DataFormat::Json.flags())
+    }
+
+    fn decode(&self, result: &GetResult) -> Result<Vec<u8>, TranscodeError> {
+        expect_format(result, &[DataFormat::Json])?;
+        Ok(result.content().to_vec())
+    }
+}
+
+pub struct RawBinaryTranscoder;
+
+impl Transcoder for RawBinaryTranscoder {
+    type Value = Vec<u8>;
+
+    fn encode(&self, value: Vec<u8>) -> (Vec<u8>, u32) {
+        (value, DataFormat::RawBinary.flags())
+    }
+
+    fn decode(&self, result: &GetResult) -> Result<Vec<u8>, TranscodeError> {
+        expect_format(result, &[DataFormat::RawBinary, DataFormat::Json])?;
+        Ok(result.content().to_vec())
+    }
+}
+
+pub struct RawStringTranscoder;
+
+impl Transcoder for RawStringTranscoder {
+    type Value = String;
+
+    fn encode(&self, value: String) -> (Vec<u8>, u32) {
+        (value.into_bytes(), DataFormat::RawString.flags())
+    }
+
+    fn decode(&self, result: &GetResult) -> Result<String, TranscodeError> {
+        expect_format(result, &[DataFormat::RawString, DataFormat::Json])?;
+        String::from_utf8(result.content().to_vec()).map_err(|_| TranscodeError::Malformed)
+    }
+}